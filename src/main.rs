@@ -1,4 +1,4 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::env;
 use std::error::Error;
 use std::io;
@@ -6,13 +6,18 @@ use std::io::{BufRead, BufReader, Write};
 use std::mem;
 use std::os::unix::net::{UnixListener, UnixStream};
 use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc;
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant, SystemTime};
 
-use i3ipc::event::inner::WindowChange;
+use i3ipc::event::inner::{WindowChange, WorkspaceChange};
 use i3ipc::event::Event;
+use i3ipc::reply::{Node, NodeType, WindowProperty};
 use i3ipc::{I3Connection, I3EventListener, Subscription};
+use serde::{Deserialize, Serialize};
 
 mod xprop;
 
@@ -25,12 +30,91 @@ const SOCKET_PATH_PROP: &str = "_I3_ALTERNATE_FOCUS_SOCKET";
 
 const SWITCH_COMMAND: &[u8] = b"switch";
 const DEBUG_COMMAND: &[u8] = b"debug";
+const MENU_COMMAND: &[u8] = b"menu";
+const COMMIT_COMMAND: &[u8] = b"commit";
+const SWITCH_FORWARD_COMMAND: &[u8] = b"switch-forward";
+const SWITCH_WORKSPACE_COMMAND: &[u8] = b"switch-workspace";
+const MENU_WORKSPACE_COMMAND: &[u8] = b"menu-workspace";
+
+/// How long to wait for a `commit` before a cycling session auto-commits.
+const CYCLE_IDLE_TIMEOUT: Duration = Duration::from_millis(750);
+
+/// Env var used to override the picker command spawned by `menu`.
+const MENU_PICKER_ENV: &str = "I3_ALTERNATE_FOCUS_PICKER";
+
+const DEFAULT_PICKER: &str = "rofi -dmenu";
+
+const EVENT_STREAM_COMMAND: &str = "event-stream";
+
+/// Version of the JSON request/response protocol understood by this server.
+const PROTOCOL_VERSION: u32 = 1;
+
+/// A JSON request sent by a client, e.g. `{"version":1,"command":"switch"}`.
+/// Plain words like `switch` (a line that doesn't parse as JSON) are still
+/// accepted so existing keybindings keep working.
+#[derive(Debug, Deserialize)]
+struct JsonRequest {
+    version: u32,
+    command: String,
+    /// Queue depth for `switch`: a 0-based index into the priority queue,
+    /// where index `0` is the window on screen. E.g. `depth: 3` focuses
+    /// the 3rd window after the focused one. Defaults to advancing the
+    /// existing cycling cursor by one.
+    #[serde(default)]
+    depth: Option<usize>,
+}
+
+/// A JSON response to a [`JsonRequest`].
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum JsonResponse {
+    Ok { data: Option<String> },
+    Error { message: String },
+}
+
+/// An event emitted on an `event-stream` connection whenever the focus
+/// queue changes.
+#[derive(Debug, Clone, Serialize)]
+struct QueueEvent {
+    version: u32,
+    event: &'static str,
+    id: i64,
+    queue: Vec<i64>,
+}
+
+impl QueueEvent {
+    fn new(event: &'static str, id: i64, windows: &VecDeque<Window>) -> QueueEvent {
+        QueueEvent {
+            version: PROTOCOL_VERSION,
+            event,
+            id,
+            queue: windows.iter().map(|win| win.id).collect(),
+        }
+    }
+}
+
+type EventSubscribers = Arc<Mutex<Vec<mpsc::Sender<String>>>>;
+
+/// Serializes `event` and forwards it to every live `event-stream`
+/// connection, dropping any whose client has gone away.
+fn publish_event(subscribers: &EventSubscribers, event: &QueueEvent) {
+    let line = match serde_json::to_string(event) {
+        Ok(line) => line,
+        Err(_) => return,
+    };
+
+    subscribers
+        .lock()
+        .unwrap()
+        .retain(|tx| tx.send(line.clone()).is_ok());
+}
 
 #[derive(Debug)]
 struct Window {
     id: i64,
     just_switched: bool,
     focused: Instant,
+    urgent: bool,
 }
 
 impl Window {
@@ -39,23 +123,54 @@ impl Window {
             id,
             just_switched: false,
             focused: Instant::now(),
+            urgent: false,
         }
     }
 }
 
-fn focus_nth(windows: &VecDeque<Window>, n: usize) -> Result<(), Box<dyn Error>> {
+/// An in-progress Alt-Tab-style cycling session: the queue is frozen at
+/// `cursor`, advancing one step per `switch` until a `commit` (or an idle
+/// timeout) picks the window and lets the queue reshuffle again.
+#[derive(Debug)]
+struct CycleSession {
+    /// Distinguishes this session from whatever may replace it in `cycle`,
+    /// so its `spawn_idle_commit` watcher doesn't act on a later session.
+    id: u64,
+    /// Focus order frozen when the session started.
+    order: Vec<i64>,
+    cursor: usize,
+    last_switch: Instant,
+    /// Id of the window the last `switch` actually focused, if any.
+    focused_id: Option<i64>,
+}
+
+/// Generation counter handed out to each new [`CycleSession`]'s `id`.
+static NEXT_CYCLE_SESSION_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Orders `windows` with urgent windows first (swayr-style "urgent first,
+/// then LRU"), keeping the existing recency order within each group.
+fn priority_order(windows: &VecDeque<Window>) -> Vec<&Window> {
+    let (mut urgent, normal): (Vec<&Window>, Vec<&Window>) =
+        windows.iter().partition(|win| win.urgent);
+
+    urgent.extend(normal);
+    urgent
+}
+
+/// Focuses the `n`th window id in `ids`, skipping past any that no longer
+/// exist, and returns the id it actually focused.
+fn focus_nth(ids: &[i64], n: usize) -> Result<i64, Box<dyn Error>> {
     let mut conn = I3Connection::connect().unwrap();
     let mut k = n;
 
     // Start from the nth window and try to change focus until it succeeds
     // (so that it skips windows which no longer exist)
-    while let Some(win) = windows.get(k) {
-        let wid = win.id;
+    while let Some(&wid) = ids.get(k) {
         let r = conn.run_command(format!("[con_id={}] focus", wid).as_str())?;
 
         if let Some(o) = r.outcomes.get(0) {
             if o.success {
-                return Ok(());
+                return Ok(wid);
             }
         }
 
@@ -65,7 +180,494 @@ fn focus_nth(windows: &VecDeque<Window>, n: usize) -> Result<(), Box<dyn Error>>
     Err(From::from(format!("Last {}nth window unavailable", n)))
 }
 
-fn cmd_server(windows: Arc<Mutex<VecDeque<Window>>>) {
+/// Escapes `name` for interpolation inside a double-quoted i3 command
+/// string, so a workspace name containing a `"` can't break out of the
+/// quoting and chain extra commands onto the same `run_command` call.
+fn escape_workspace_name(name: &str) -> String {
+    name.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn focus_nth_workspace(workspaces: &[String], n: usize) -> Result<(), Box<dyn Error>> {
+    let mut conn = I3Connection::connect().unwrap();
+    let mut k = n;
+
+    // Start from the nth workspace and try to switch until it succeeds
+    // (so that it skips workspaces which no longer exist)
+    while let Some(name) = workspaces.get(k) {
+        let name = escape_workspace_name(name);
+        let r = conn.run_command(format!("workspace \"{}\"", name).as_str())?;
+
+        if let Some(o) = r.outcomes.get(0) {
+            if o.success {
+                return Ok(());
+            }
+        }
+
+        k += 1;
+    }
+
+    Err(From::from(format!("Last {}nth workspace unavailable", n)))
+}
+
+/// Ends a cycling session, moving the focused window to the front of the
+/// queue and publishing a `committed` event.
+fn commit_session(
+    windows: &Mutex<VecDeque<Window>>,
+    cycle: &Mutex<Option<CycleSession>>,
+    subscribers: &EventSubscribers,
+) {
+    let session = match cycle.lock().unwrap().take() {
+        Some(session) => session,
+        None => return,
+    };
+
+    let target = session
+        .focused_id
+        .or_else(|| session.order.get(session.cursor).copied());
+
+    if let Some(id) = target {
+        let mut winc = windows.lock().unwrap();
+        winc.retain(|win| win.id != id);
+
+        let mut win = Window::new(id);
+        win.just_switched = true;
+        winc.push_front(win);
+
+        publish_event(subscribers, &QueueEvent::new("committed", id, &winc));
+    }
+}
+
+/// Starts a cycling session at `depth`, or if one is already in progress
+/// and no explicit `depth` was given, advances its cursor by one step
+/// deeper (the original hold-Tab behavior).
+fn begin_or_advance_cycle(
+    windows: &Arc<Mutex<VecDeque<Window>>>,
+    cycle: &Arc<Mutex<Option<CycleSession>>>,
+    subscribers: &EventSubscribers,
+    depth: Option<usize>,
+) -> usize {
+    let mut cyc = cycle.lock().unwrap();
+
+    match cyc.as_mut() {
+        Some(session) => {
+            session.cursor = depth.unwrap_or(session.cursor + 1);
+            session.last_switch = Instant::now();
+            session.cursor
+        }
+
+        None => {
+            let cursor = depth.unwrap_or(1);
+            // The focused window stays at index 0; urgent windows follow it,
+            // ahead of the rest of the recency order.
+            let order = {
+                let winc = windows.lock().unwrap();
+                let focused_id = winc.front().map(|win| win.id);
+                focused_id
+                    .into_iter()
+                    .chain(
+                        priority_order(&winc)
+                            .iter()
+                            .map(|win| win.id)
+                            .filter(|&id| Some(id) != focused_id),
+                    )
+                    .collect()
+            };
+            let id = NEXT_CYCLE_SESSION_ID.fetch_add(1, Ordering::Relaxed);
+
+            *cyc = Some(CycleSession {
+                id,
+                order,
+                cursor,
+                last_switch: Instant::now(),
+                focused_id: None,
+            });
+
+            spawn_idle_commit(windows.clone(), cycle.clone(), subscribers.clone(), id);
+
+            cursor
+        }
+    }
+}
+
+/// Steps an in-progress cycling session's cursor towards more-recently
+/// focused entries (`switch-forward`), all the way back to index 0 (the
+/// window that was focused before the session began). Returns `None` if
+/// there is no active session to step.
+fn step_cycle_forward(cycle: &Arc<Mutex<Option<CycleSession>>>) -> Option<usize> {
+    let mut cyc = cycle.lock().unwrap();
+    let session = cyc.as_mut()?;
+
+    session.cursor = session.cursor.saturating_sub(1);
+    session.last_switch = Instant::now();
+
+    Some(session.cursor)
+}
+
+/// Focuses `cursor` within the active session's frozen order and records
+/// the id it actually focused, for `commit_session` to use later.
+fn focus_cycle_cursor(
+    cycle: &Arc<Mutex<Option<CycleSession>>>,
+    cursor: usize,
+) -> Result<(), Box<dyn Error>> {
+    let order = match cycle.lock().unwrap().as_ref() {
+        Some(session) => session.order.clone(),
+        None => return Err(From::from("No active cycling session")),
+    };
+
+    let id = focus_nth(&order, cursor)?;
+
+    if let Some(session) = cycle.lock().unwrap().as_mut() {
+        session.focused_id = Some(id);
+    }
+
+    Ok(())
+}
+
+/// Watches the cycling session with the given `id` and auto-commits it once
+/// `switch` has not been called again for `CYCLE_IDLE_TIMEOUT`, in case the
+/// client never sends `commit` (e.g. a misconfigured keybinding). Exits
+/// without committing as soon as `id` is no longer the session in `cycle`
+/// (already committed, or replaced by a newer session), so stale watchers
+/// don't pile up polling whatever session happens to be active.
+fn spawn_idle_commit(
+    windows: Arc<Mutex<VecDeque<Window>>>,
+    cycle: Arc<Mutex<Option<CycleSession>>>,
+    subscribers: EventSubscribers,
+    id: u64,
+) {
+    thread::spawn(move || loop {
+        thread::sleep(CYCLE_IDLE_TIMEOUT);
+
+        let idle = match cycle.lock().unwrap().as_ref() {
+            Some(session) if session.id == id => {
+                session.last_switch.elapsed() >= CYCLE_IDLE_TIMEOUT
+            }
+            Some(_) | None => break,
+        };
+
+        if idle {
+            commit_session(&windows, &cycle, &subscribers);
+            break;
+        }
+    });
+}
+
+/// Walks the i3 tree collecting a human-readable `title (app — workspace)`
+/// label for every container id in `ids`.
+fn collect_labels(
+    node: &Node,
+    workspace: Option<&str>,
+    ids: &HashSet<i64>,
+    labels: &mut HashMap<i64, String>,
+) {
+    let workspace = match node.nodetype {
+        NodeType::Workspace => node.name.as_deref(),
+        _ => workspace,
+    };
+
+    if ids.contains(&node.id) {
+        let props = node.window_properties.as_ref();
+        let title = props
+            .and_then(|p| p.get(&WindowProperty::Title).cloned())
+            .or_else(|| node.name.clone())
+            .unwrap_or_else(|| "?".to_string());
+        let app = props
+            .and_then(|p| p.get(&WindowProperty::Class).cloned())
+            .unwrap_or_else(|| "?".to_string());
+
+        labels.insert(
+            node.id,
+            format!("{} ({} — {})", title, app, workspace.unwrap_or("?")),
+        );
+    }
+
+    for child in node.nodes.iter().chain(node.floating_nodes.iter()) {
+        collect_labels(child, workspace, ids, labels);
+    }
+}
+
+/// Builds the `(id, label)` pairs for the window picker: urgent windows
+/// first, then the rest most-recent first, with the currently focused
+/// window last.
+fn build_menu_entries(windows: &VecDeque<Window>) -> Result<Vec<(i64, String)>, Box<dyn Error>> {
+    let mut conn = I3Connection::connect()?;
+    let tree = conn.get_tree()?;
+
+    let ids: HashSet<i64> = windows.iter().map(|w| w.id).collect();
+    let mut labels = HashMap::new();
+    collect_labels(&tree, None, &ids, &mut labels);
+
+    let focused_id = windows.front().map(|w| w.id);
+    let mut ordered: Vec<&Window> = priority_order(windows)
+        .into_iter()
+        .filter(|win| Some(win.id) != focused_id)
+        .collect();
+    ordered.extend(windows.front());
+
+    Ok(ordered
+        .into_iter()
+        .map(|win| {
+            let label = labels.get(&win.id).cloned().unwrap_or_else(|| "?".to_string());
+            (win.id, label)
+        })
+        .collect())
+}
+
+/// Spawns the configured picker (`$I3_ALTERNATE_FOCUS_PICKER`, or rofi by
+/// default) with `input` on its stdin and returns what it wrote to stdout.
+fn spawn_picker(input: String) -> Result<String, Box<dyn Error>> {
+    let picker = env::var(MENU_PICKER_ENV).unwrap_or_else(|_| DEFAULT_PICKER.to_string());
+    let mut parts = picker.split_whitespace();
+    let program = parts.next().ok_or("Empty picker command")?;
+
+    let mut child = Command::new(program)
+        .args(parts)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    child
+        .stdin
+        .take()
+        .ok_or("Picker has no stdin")?
+        .write_all(input.as_bytes())?;
+
+    let output = child.wait_with_output()?;
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Spawns the configured picker with `entries` and focuses whatever is
+/// chosen.
+fn run_menu(entries: Vec<(i64, String)>) -> Result<(), Box<dyn Error>> {
+    let mut conn = I3Connection::connect()?;
+
+    let mut input = String::new();
+    for (id, label) in &entries {
+        input.push_str(&format!("{}\t{}\n", id, label));
+    }
+
+    let selection = spawn_picker(input)?;
+    let con_id = selection.split('\t').next().unwrap_or("").trim();
+
+    if con_id.is_empty() {
+        return Ok(());
+    }
+
+    conn.run_command(format!("[con_id={}] focus", con_id.parse::<i64>()?).as_str())?;
+
+    Ok(())
+}
+
+fn run_menu_command(windows: &Mutex<VecDeque<Window>>) -> Result<(), Box<dyn Error>> {
+    let entries = {
+        let winc = windows.lock().unwrap();
+        build_menu_entries(&winc)?
+    };
+
+    run_menu(entries)
+}
+
+/// Builds the ordered workspace names for the picker, most-recent first
+/// with the currently focused workspace last.
+fn build_menu_workspace_entries(workspaces: &VecDeque<String>) -> Vec<String> {
+    let mut ordered: Vec<&String> = workspaces.iter().skip(1).collect();
+    ordered.extend(workspaces.front());
+
+    ordered.into_iter().cloned().collect()
+}
+
+/// Spawns the configured picker with `names` and switches to whatever is
+/// chosen.
+fn run_menu_workspace(names: Vec<String>) -> Result<(), Box<dyn Error>> {
+    let mut conn = I3Connection::connect()?;
+
+    let mut input = String::new();
+    for name in &names {
+        input.push_str(&format!("{}\n", name));
+    }
+
+    let selection = spawn_picker(input)?;
+    let name = selection.trim();
+
+    if name.is_empty() {
+        return Ok(());
+    }
+
+    conn.run_command(format!("workspace \"{}\"", escape_workspace_name(name)).as_str())?;
+
+    Ok(())
+}
+
+fn run_menu_workspace_command(workspaces: &Mutex<VecDeque<String>>) -> Result<(), Box<dyn Error>> {
+    let names = {
+        let wsc = workspaces.lock().unwrap();
+        build_menu_workspace_entries(&wsc)
+    };
+
+    run_menu_workspace(names)
+}
+
+/// Runs one of the legacy plain-word commands (`switch [depth]`,
+/// `switch-forward`, `commit`, `debug`, `menu`, `switch-workspace`,
+/// `menu-workspace`), writing any output straight to `stream` as before.
+fn handle_legacy_command(
+    line: &str,
+    stream: &mut UnixStream,
+    windows: &Arc<Mutex<VecDeque<Window>>>,
+    cycle: &Arc<Mutex<Option<CycleSession>>>,
+    workspaces: &Arc<Mutex<VecDeque<String>>>,
+    subscribers: &EventSubscribers,
+) {
+    let mut words = line.split_whitespace();
+    let command = words.next().unwrap_or("").as_bytes();
+
+    match command {
+        SWITCH_COMMAND => {
+            let depth = words.next().and_then(|arg| arg.parse().ok());
+            let cursor = begin_or_advance_cycle(windows, cycle, subscribers, depth);
+
+            let _ = focus_cycle_cursor(cycle, cursor);
+        }
+
+        SWITCH_FORWARD_COMMAND => {
+            if let Some(cursor) = step_cycle_forward(cycle) {
+                let _ = focus_cycle_cursor(cycle, cursor);
+            }
+        }
+
+        COMMIT_COMMAND => {
+            commit_session(windows, cycle, subscribers);
+        }
+
+        DEBUG_COMMAND => {
+            let winc = windows.lock().unwrap();
+            let _ = write!(stream, "{:#?}\n", winc);
+        }
+
+        MENU_COMMAND => {
+            if let Err(e) = run_menu_command(windows) {
+                let _ = write!(stream, "menu failed: {}\n", e);
+            }
+        }
+
+        SWITCH_WORKSPACE_COMMAND => {
+            let names: Vec<String> = workspaces.lock().unwrap().iter().cloned().collect();
+            let _ = focus_nth_workspace(&names, 1);
+        }
+
+        MENU_WORKSPACE_COMMAND => {
+            if let Err(e) = run_menu_workspace_command(workspaces) {
+                let _ = write!(stream, "menu-workspace failed: {}\n", e);
+            }
+        }
+
+        _ => {
+            let _ = stream.write_all(b"Invalid command\n");
+        }
+    }
+}
+
+/// Keeps `stream` open and forwards every published [`QueueEvent`] to it,
+/// one JSON object per line, until the client disconnects.
+fn stream_events(stream: &mut UnixStream, subscribers: &EventSubscribers) {
+    let (tx, rx) = mpsc::channel();
+    subscribers.lock().unwrap().push(tx);
+
+    for line in rx {
+        if writeln!(stream, "{}", line).is_err() {
+            break;
+        }
+    }
+}
+
+fn send_json_response(stream: &mut UnixStream, response: &JsonResponse) {
+    if let Ok(line) = serde_json::to_string(response) {
+        let _ = writeln!(stream, "{}", line);
+    }
+}
+
+/// Runs a versioned JSON request, responding with a single `JsonResponse`
+/// line (`event-stream` instead keeps streaming events over `stream`).
+fn handle_json_request(
+    req: JsonRequest,
+    stream: &mut UnixStream,
+    windows: &Arc<Mutex<VecDeque<Window>>>,
+    cycle: &Arc<Mutex<Option<CycleSession>>>,
+    workspaces: &Arc<Mutex<VecDeque<String>>>,
+    subscribers: &EventSubscribers,
+) {
+    if req.version != PROTOCOL_VERSION {
+        send_json_response(
+            stream,
+            &JsonResponse::Error {
+                message: format!("Unsupported protocol version {}", req.version),
+            },
+        );
+        return;
+    }
+
+    if req.command == EVENT_STREAM_COMMAND {
+        send_json_response(stream, &JsonResponse::Ok { data: None });
+        stream_events(stream, subscribers);
+        return;
+    }
+
+    let result = match req.command.as_str() {
+        "switch" => {
+            let cursor = begin_or_advance_cycle(windows, cycle, subscribers, req.depth);
+            focus_cycle_cursor(cycle, cursor)
+        }
+
+        "switch-forward" => match step_cycle_forward(cycle) {
+            Some(cursor) => focus_cycle_cursor(cycle, cursor),
+            None => Err(From::from("No active cycling session")),
+        },
+
+        "commit" => {
+            commit_session(windows, cycle, subscribers);
+            Ok(())
+        }
+
+        "debug" => {
+            let winc = windows.lock().unwrap();
+            send_json_response(
+                stream,
+                &JsonResponse::Ok {
+                    data: Some(format!("{:#?}", winc)),
+                },
+            );
+            return;
+        }
+
+        "menu" => run_menu_command(windows),
+
+        "switch-workspace" => {
+            let names: Vec<String> = workspaces.lock().unwrap().iter().cloned().collect();
+            focus_nth_workspace(&names, 1)
+        }
+
+        "menu-workspace" => run_menu_workspace_command(workspaces),
+
+        other => Err(From::from(format!("Unknown command {:?}", other))),
+    };
+
+    send_json_response(
+        stream,
+        &match result {
+            Ok(()) => JsonResponse::Ok { data: None },
+            Err(e) => JsonResponse::Error {
+                message: e.to_string(),
+            },
+        },
+    );
+}
+
+fn cmd_server(
+    windows: Arc<Mutex<VecDeque<Window>>>,
+    cycle: Arc<Mutex<Option<CycleSession>>>,
+    workspaces: Arc<Mutex<VecDeque<String>>>,
+    subscribers: EventSubscribers,
+) {
     let socket = {
         let mut base = match env::var("XDG_RUNTIME_DIR") {
             Ok(path) => PathBuf::from(path),
@@ -95,27 +697,33 @@ fn cmd_server(windows: Arc<Mutex<VecDeque<Window>>>) {
     for stream in listener.incoming() {
         if let Ok(mut stream) = stream {
             let windows = windows.clone();
+            let cycle = cycle.clone();
+            let workspaces = workspaces.clone();
+            let subscribers = subscribers.clone();
 
             thread::spawn(move || {
                 let mut reader = BufReader::new(stream.try_clone().unwrap()).lines();
                 let line = reader.next();
-                match line {
-                    Some(Ok(line)) if line.as_bytes() == SWITCH_COMMAND => {
-                        let mut winc = windows.lock().unwrap();
-
-                        // Ignore MIN_FOCUS if we alternate focus between two
-                        // windows
-                        winc.front_mut()
-                            .iter_mut()
-                            .for_each(|win| win.just_switched = true);
 
-                        let _ = focus_nth(&winc, 1);
-                    }
-
-                    Some(Ok(line)) if line.as_bytes() == DEBUG_COMMAND => {
-                        let winc = windows.lock().unwrap();
-                        let _ = write!(&mut stream, "{:#?}\n", winc);
-                    }
+                match line {
+                    Some(Ok(line)) => match serde_json::from_str::<JsonRequest>(&line) {
+                        Ok(req) => handle_json_request(
+                            req,
+                            &mut stream,
+                            &windows,
+                            &cycle,
+                            &workspaces,
+                            &subscribers,
+                        ),
+                        Err(_) => handle_legacy_command(
+                            &line,
+                            &mut stream,
+                            &windows,
+                            &cycle,
+                            &workspaces,
+                            &subscribers,
+                        ),
+                    },
 
                     _ => {
                         let _ = stream.write_all(b"Invalid command\n");
@@ -143,10 +751,42 @@ fn get_focused_window() -> Result<i64, ()> {
     Ok(node.id)
 }
 
+fn get_focused_workspace() -> Result<String, ()> {
+    let mut conn = I3Connection::connect().unwrap();
+    let mut node = conn.get_tree().unwrap();
+    let mut workspace = None;
+
+    loop {
+        if node.nodetype == NodeType::Workspace {
+            workspace = node.name.clone();
+        }
+
+        if node.focused {
+            break;
+        }
+
+        let fid = node.focus.into_iter().nth(0).ok_or(())?;
+        node = node
+            .nodes
+            .into_iter()
+            .filter(|n| n.id == fid)
+            .nth(0)
+            .ok_or(())?;
+    }
+
+    workspace.ok_or(())
+}
+
 fn focus_server() {
     let mut listener = I3EventListener::connect().unwrap();
     let windows = Arc::new(Mutex::new(VecDeque::new()));
     let windowsc = Arc::clone(&windows);
+    let cycle = Arc::new(Mutex::new(None));
+    let cyclec = Arc::clone(&cycle);
+    let workspaces = Arc::new(Mutex::new(VecDeque::new()));
+    let workspacesc = Arc::clone(&workspaces);
+    let subscribers: EventSubscribers = Arc::new(Mutex::new(Vec::new()));
+    let subscribersc = Arc::clone(&subscribers);
 
     // Add the current focused window to bootstrap the list
     get_focused_window()
@@ -156,22 +796,42 @@ fn focus_server() {
         })
         .ok();
 
-    thread::spawn(|| cmd_server(windowsc));
+    // Add the current focused workspace to bootstrap its list
+    get_focused_workspace()
+        .map(|name| {
+            let mut workspaces = workspaces.lock().unwrap();
+            workspaces.push_front(name);
+        })
+        .ok();
+
+    thread::spawn(|| cmd_server(windowsc, cyclec, workspacesc, subscribersc));
 
     // Listens to i3 event
-    let subs = [Subscription::Window];
+    let subs = [Subscription::Window, Subscription::Workspace];
     listener.subscribe(&subs).unwrap();
 
     for event in listener.listen() {
         match event.unwrap() {
-            Event::WindowEvent(e) => {
-                if let WindowChange::Focus = e.change {
+            Event::WindowEvent(e) => match e.change {
+                WindowChange::Focus => {
+                    if cycle.lock().unwrap().is_some() {
+                        // This focus event was caused by our own focus_nth
+                        // call while a cycling session is in progress: keep
+                        // the queue frozen until the session commits.
+                        continue;
+                    }
+
                     let mut windows = windows.lock().unwrap();
 
                     if let Some(win) = windows.front_mut() {
                         if !mem::replace(&mut win.just_switched, false) {
                             if win.focused.elapsed() < MIN_FOCUS {
-                                let _ = windows.pop_front();
+                                if let Some(removed) = windows.pop_front() {
+                                    publish_event(
+                                        &subscribers,
+                                        &QueueEvent::new("removed", removed.id, &windows),
+                                    );
+                                }
                             }
                         }
                     }
@@ -179,9 +839,39 @@ fn focus_server() {
                     // dedupe, push front and truncate
                     windows.retain(|v| v.id != e.container.id);
                     windows.push_front(Window::new(e.container.id));
-                    windows.truncate(BUFFER_SIZE);
+                    publish_event(&subscribers, &QueueEvent::new("focus", e.container.id, &windows));
+
+                    if windows.len() > BUFFER_SIZE {
+                        windows.truncate(BUFFER_SIZE);
+                        publish_event(
+                            &subscribers,
+                            &QueueEvent::new("truncated", e.container.id, &windows),
+                        );
+                    }
+                }
+
+                WindowChange::Urgent => {
+                    let mut windows = windows.lock().unwrap();
+
+                    if let Some(win) = windows.iter_mut().find(|w| w.id == e.container.id) {
+                        win.urgent = e.container.urgent;
+                    }
+                }
+
+                _ => (),
+            },
+
+            Event::WorkspaceEvent(e) => {
+                if let WorkspaceChange::Focus = e.change {
+                    if let Some(name) = e.current.and_then(|n| n.name) {
+                        let mut workspaces = workspaces.lock().unwrap();
+                        workspaces.retain(|w| *w != name);
+                        workspaces.push_front(name);
+                        workspaces.truncate(BUFFER_SIZE);
+                    }
                 }
             }
+
             _ => unreachable!(),
         }
     }
@@ -204,7 +894,11 @@ fn main() {
             focus_client(&arg);
         }
         _ => {
-            eprintln!("Expected argument: server, switch, debug");
+            eprintln!(
+                "Expected argument: server, switch, switch-forward, commit, menu, \
+                 switch-workspace, menu-workspace, debug, or a JSON request such as \
+                 {{\"version\":1,\"command\":\"event-stream\"}}"
+            );
         }
     }
 }